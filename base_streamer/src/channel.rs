@@ -0,0 +1,7 @@
+//! Behavior common to every channel type, regardless of the sample value it carries.
+
+pub trait BaseChan {
+    fn name(&self) -> String;
+    fn last_instr_end_time(&self) -> f64;
+    fn clear_edit_cache(&mut self);
+}
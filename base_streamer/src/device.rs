@@ -0,0 +1,8 @@
+//! Behavior common to every device type, regardless of the channels it hosts.
+
+pub trait BaseDev {
+    fn name(&self) -> &str;
+    fn samp_rate(&self) -> f64;
+    fn last_instr_end_time(&self) -> f64;
+    fn clear_edit_cache(&mut self);
+}
@@ -0,0 +1,79 @@
+//! The function kinds a channel instruction can evaluate, exposed to Python as
+//! `FnBoxF64`/`FnBoxBool`.
+
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FnKindF64 {
+    Const { val: f64 },
+    Linear { slope: f64, intercept: f64 },
+    Sine { amp: f64, freq: f64, phase: f64, offset: f64 },
+}
+
+impl FnKindF64 {
+    /// `t` is relative to the instruction's own start time, not the channel's
+    /// absolute timeline — callers (`eval_nsamps`) must subtract the instruction's
+    /// start time before calling this. That lets the same `(slope, intercept)` or
+    /// `(freq, phase)` be scheduled at any start time and produce the same curve.
+    pub fn eval(&self, t: f64) -> f64 {
+        match *self {
+            FnKindF64::Const { val } => val,
+            FnKindF64::Linear { slope, intercept } => slope * t + intercept,
+            FnKindF64::Sine { amp, freq, phase, offset } => {
+                amp * (2.0 * std::f64::consts::PI * freq * t + phase).sin() + offset
+            }
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct FnBoxF64 {
+    pub inner: FnKindF64,
+}
+
+#[pymethods]
+impl FnBoxF64 {
+    #[staticmethod]
+    pub fn const_val(val: f64) -> Self {
+        Self { inner: FnKindF64::Const { val } }
+    }
+
+    #[staticmethod]
+    pub fn linear(slope: f64, intercept: f64) -> Self {
+        Self { inner: FnKindF64::Linear { slope, intercept } }
+    }
+
+    #[staticmethod]
+    pub fn sine(amp: f64, freq: f64, phase: f64, offset: f64) -> Self {
+        Self { inner: FnKindF64::Sine { amp, freq, phase, offset } }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FnKindBool {
+    Const { val: bool },
+}
+
+impl FnKindBool {
+    pub fn eval(&self, _t: f64) -> bool {
+        match *self {
+            FnKindBool::Const { val } => val,
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct FnBoxBool {
+    pub inner: FnKindBool,
+}
+
+#[pymethods]
+impl FnBoxBool {
+    #[staticmethod]
+    pub fn const_val(val: bool) -> Self {
+        Self { inner: FnKindBool::Const { val } }
+    }
+}
@@ -0,0 +1,8 @@
+//! Traits and Python-facing building blocks shared by every instrument-specific
+//! streamer crate (timing/run-control vocabulary, per-channel/per-device
+//! bookkeeping, and the serializable instruction-function library).
+
+pub mod channel;
+pub mod device;
+pub mod streamer;
+pub mod fn_lib_tools;
@@ -0,0 +1,24 @@
+//! Run-control and compile-timing vocabulary shared by every streamer, independent
+//! of which devices/channels it hosts.
+
+pub trait BaseStreamer {
+    fn get_starts_last(&self) -> Option<String>;
+    fn set_starts_last(&mut self, name: Option<String>);
+
+    fn get_ref_clk_provider(&self) -> Option<(String, String)>;
+    fn set_ref_clk_provider(&mut self, provider: Option<(String, String)>);
+
+    fn reset_all(&self) -> Result<(), String>;
+
+    fn last_instr_end_time(&self) -> f64;
+    fn total_run_time(&self) -> f64;
+
+    fn compile(&mut self, stop_time: Option<f64>) -> Result<f64, String>;
+    fn is_fresh_compiled(&self) -> bool;
+    fn clear_edit_cache(&mut self);
+    fn add_reset_instr(&mut self, reset_time: Option<f64>) -> Result<(), String>;
+
+    fn cfg_run_(&mut self, bufsize_ms: f64) -> Result<(), String>;
+    fn stream_run_(&mut self, calc_next: bool) -> Result<(), String>;
+    fn close_run_(&mut self) -> Result<(), String>;
+}
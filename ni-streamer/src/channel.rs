@@ -0,0 +1,474 @@
+//! NI channel types. Each channel owns a sorted instruction list, evaluated
+//! pointwise by `calc_nsamps`, plus its default/reset values.
+
+use serde::{Deserialize, Serialize};
+
+use base_streamer::channel::BaseChan;
+use base_streamer::fn_lib_tools::{FnKindBool, FnKindF64};
+
+type RawInstr<K> = (f64, Option<(f64, bool)>, K);
+
+/// `dur_spec = Some((dur, true))` gives the instruction a fixed `[t, t+dur)` window.
+/// `Some((dur, false))` or `None` lets it run open-ended until the next instruction
+/// starts (or to the end of the window if it is the last one); `dur` is then only
+/// used to reject an overlap with whatever instruction follows.
+fn fixed_end(t: f64, dur_spec: &Option<(f64, bool)>) -> Option<f64> {
+    match dur_spec {
+        Some((dur, true)) => Some(t + dur),
+        _ => None,
+    }
+}
+
+fn min_next_start(t: f64, dur_spec: &Option<(f64, bool)>) -> f64 {
+    match dur_spec {
+        Some((dur, _)) => t + dur,
+        None => t,
+    }
+}
+
+fn push_instr<K>(instrs: &mut Vec<RawInstr<K>>, t: f64, dur_spec: Option<(f64, bool)>, kind: K) -> Result<(), String> {
+    if let Some((last_t, last_dur_spec, _)) = instrs.last() {
+        if t <= *last_t {
+            return Err(format!(
+                "New instruction at t={t} does not come after the last instruction at t={last_t}"
+            ));
+        }
+        let min_start = min_next_start(*last_t, last_dur_spec);
+        if t < min_start {
+            return Err(format!(
+                "New instruction at t={t} overlaps the previous instruction, which runs until at least t={min_start}"
+            ));
+        }
+    }
+    instrs.push((t, dur_spec, kind));
+    Ok(())
+}
+
+fn last_instr_end_time<K>(instrs: &[RawInstr<K>]) -> f64 {
+    instrs
+        .last()
+        .map(|(t, dur_spec, _)| fixed_end(*t, dur_spec).unwrap_or(*t))
+        .unwrap_or(0.0)
+}
+
+fn eval_nsamps<K, V: Copy>(
+    instrs: &[RawInstr<K>],
+    dflt_val: V,
+    rst_val: V,
+    eval: impl Fn(&K, f64) -> V,
+    n_samps: usize,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<Vec<V>, String> {
+    let st = start_time.unwrap_or(0.0);
+    let et = end_time.unwrap_or_else(|| last_instr_end_time(instrs));
+    if et < st {
+        return Err(format!("end_time ({et}) must not be earlier than start_time ({st})"));
+    }
+    if n_samps == 0 {
+        return Ok(Vec::new());
+    }
+
+    let step = (et - st) / n_samps as f64;
+    let mut out = Vec::with_capacity(n_samps);
+    for i in 0..n_samps {
+        let t = st + step * i as f64;
+
+        // Number of instructions with a start time <= t; the last of them is at idx - 1.
+        let idx = instrs.partition_point(|(instr_t, ..)| *instr_t <= t);
+        let val = if idx == 0 {
+            dflt_val
+        } else {
+            let (instr_t, dur_spec, kind) = &instrs[idx - 1];
+            let still_active = match fixed_end(*instr_t, dur_spec) {
+                Some(end) => t < end,
+                None => true,
+            };
+            if still_active {
+                // `kind` is evaluated relative to its own start time, not the
+                // absolute schedule timeline: an instruction scheduled at t=5
+                // sees the same t=0 origin it would if it had been scheduled at
+                // t=0, so the same `(func, dur_spec)` pair can be reused verbatim
+                // at any start time.
+                eval(kind, t - instr_t)
+            } else {
+                rst_val
+            }
+        };
+        out.push(val);
+    }
+    Ok(out)
+}
+
+/// Transform applied to a raw function-evaluated AO sample to compensate for a
+/// channel's analog front-end, before the result is clamped to `AOChan::limits`.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum Calib {
+    #[default]
+    None,
+    Linear { gain: f64, offset: f64 },
+    /// Piecewise-linear, interpolated/extrapolated by clamping to the table's ends.
+    Table { points: Vec<(f64, f64)> },
+}
+
+impl Calib {
+    fn apply(&self, val: f64) -> f64 {
+        match self {
+            Calib::None => val,
+            Calib::Linear { gain, offset } => gain * val + offset,
+            Calib::Table { points } => interpolate(points, val),
+        }
+    }
+}
+
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    if x <= points[0].0 {
+        return points[0].1;
+    }
+    let last = points.len() - 1;
+    if x >= points[last].0 {
+        return points[last].1;
+    }
+    let idx = points.partition_point(|(px, _)| *px <= x);
+    let (x0, y0) = points[idx - 1];
+    let (x1, y1) = points[idx];
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AOChan {
+    name: String,
+    samp_rate: f64,
+    dflt_val: f64,
+    rst_val: f64,
+    instrs: Vec<RawInstr<FnKindF64>>,
+    calib: Calib,
+    limits: Option<(f64, f64)>,
+}
+
+impl AOChan {
+    pub fn new(chan_idx: usize, samp_rate: f64, dflt_val: f64, rst_val: f64) -> Self {
+        Self {
+            name: format!("ao{chan_idx}"),
+            samp_rate,
+            dflt_val,
+            rst_val,
+            instrs: Vec::new(),
+            calib: Calib::None,
+            limits: None,
+        }
+    }
+
+    pub fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    pub fn dflt_val(&self) -> f64 {
+        self.dflt_val
+    }
+
+    pub fn rst_val(&self) -> f64 {
+        self.rst_val
+    }
+
+    pub fn add_instr(&mut self, kind: FnKindF64, t: f64, dur_spec: Option<(f64, bool)>) -> Result<(), String> {
+        push_instr(&mut self.instrs, t, dur_spec, kind)
+    }
+
+    pub fn set_calib(&mut self, gain: f64, offset: f64) {
+        self.calib = Calib::Linear { gain, offset };
+    }
+
+    pub fn set_calib_table(&mut self, points: Vec<(f64, f64)>) -> Result<(), String> {
+        if points.len() < 2 {
+            return Err(format!("Calibration table must have at least 2 points, got {}", points.len()));
+        }
+        for pair in points.windows(2) {
+            if pair[1].0 <= pair[0].0 {
+                return Err(format!(
+                    "Calibration table x-values must be strictly increasing, but {} does not come after {}",
+                    pair[1].0, pair[0].0
+                ));
+            }
+        }
+        self.calib = Calib::Table { points };
+        Ok(())
+    }
+
+    pub fn set_limits(&mut self, min: f64, max: f64) -> Result<(), String> {
+        if min > max {
+            return Err(format!("min ({min}) must not be greater than max ({max})"));
+        }
+        self.limits = Some((min, max));
+        Ok(())
+    }
+
+    pub fn calc_nsamps(&self, n_samps: usize, start_time: Option<f64>, end_time: Option<f64>) -> Result<Vec<f64>, String> {
+        let mut samps = eval_nsamps(&self.instrs, self.dflt_val, self.rst_val, |kind, t| kind.eval(t), n_samps, start_time, end_time)?;
+        for samp in samps.iter_mut() {
+            *samp = self.calib.apply(*samp);
+            if let Some((min, max)) = self.limits {
+                *samp = samp.clamp(min, max);
+            }
+        }
+        Ok(samps)
+    }
+}
+
+impl BaseChan for AOChan {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        last_instr_end_time(&self.instrs)
+    }
+
+    fn clear_edit_cache(&mut self) {
+        self.instrs.clear();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DOChan {
+    name: String,
+    samp_rate: f64,
+    dflt_val: bool,
+    rst_val: bool,
+    instrs: Vec<RawInstr<FnKindBool>>,
+}
+
+impl DOChan {
+    pub fn new(port_idx: usize, line_idx: usize, samp_rate: f64, dflt_val: bool, rst_val: bool) -> Self {
+        Self {
+            name: format!("port{port_idx}/line{line_idx}"),
+            samp_rate,
+            dflt_val,
+            rst_val,
+            instrs: Vec::new(),
+        }
+    }
+
+    pub fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    pub fn dflt_val(&self) -> bool {
+        self.dflt_val
+    }
+
+    pub fn rst_val(&self) -> bool {
+        self.rst_val
+    }
+
+    pub fn add_instr(&mut self, kind: FnKindBool, t: f64, dur_spec: Option<(f64, bool)>) -> Result<(), String> {
+        push_instr(&mut self.instrs, t, dur_spec, kind)
+    }
+
+    pub fn calc_nsamps(&self, n_samps: usize, start_time: Option<f64>, end_time: Option<f64>) -> Result<Vec<bool>, String> {
+        eval_nsamps(&self.instrs, self.dflt_val, self.rst_val, |kind, t| kind.eval(t), n_samps, start_time, end_time)
+    }
+}
+
+impl BaseChan for DOChan {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        last_instr_end_time(&self.instrs)
+    }
+
+    fn clear_edit_cache(&mut self) {
+        self.instrs.clear();
+    }
+}
+
+/// Buffered-acquisition channels don't schedule output instructions; they just hold
+/// whatever was most recently staged into them by a run (see `Streamer::execute_stage`),
+/// to be read back (copied, not removed) by `read_nsamps`/`read_counts`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AIChan {
+    name: String,
+    samp_rate: f64,
+    #[serde(skip)]
+    acquired: Vec<f64>,
+}
+
+impl AIChan {
+    pub fn new(chan_idx: usize, samp_rate: f64) -> Self {
+        Self {
+            name: format!("ai{chan_idx}"),
+            samp_rate,
+            acquired: Vec::new(),
+        }
+    }
+
+    pub fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    pub fn set_acquired(&mut self, samps: Vec<f64>) {
+        self.acquired = samps;
+    }
+
+    pub fn read_nsamps(&self, n_samps: usize) -> Result<Vec<f64>, String> {
+        if n_samps > self.acquired.len() {
+            return Err(format!(
+                "Requested {n_samps} samples from AI channel {}, but only {} have been acquired; run the device before reading",
+                self.name,
+                self.acquired.len()
+            ));
+        }
+        Ok(self.acquired[..n_samps].to_vec())
+    }
+}
+
+impl BaseChan for AIChan {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        0.0
+    }
+
+    fn clear_edit_cache(&mut self) {
+        self.acquired.clear();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIChan {
+    name: String,
+    samp_rate: f64,
+    #[serde(skip)]
+    acquired: Vec<bool>,
+}
+
+impl DIChan {
+    pub fn new(port_idx: usize, line_idx: usize, samp_rate: f64) -> Self {
+        Self {
+            name: format!("port{port_idx}/line{line_idx}"),
+            samp_rate,
+            acquired: Vec::new(),
+        }
+    }
+
+    pub fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    pub fn set_acquired(&mut self, samps: Vec<bool>) {
+        self.acquired = samps;
+    }
+
+    pub fn read_nsamps(&self, n_samps: usize) -> Result<Vec<bool>, String> {
+        if n_samps > self.acquired.len() {
+            return Err(format!(
+                "Requested {n_samps} samples from DI channel {}, but only {} have been acquired; run the device before reading",
+                self.name,
+                self.acquired.len()
+            ));
+        }
+        Ok(self.acquired[..n_samps].to_vec())
+    }
+}
+
+impl BaseChan for DIChan {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        0.0
+    }
+
+    fn clear_edit_cache(&mut self) {
+        self.acquired.clear();
+    }
+}
+
+/// A DI-hosted edge counter: counts rising or falling edges on `terminal` instead
+/// of sampling a line level.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EdgeCounterChan {
+    name: String,
+    samp_rate: f64,
+    terminal: String,
+    rising_edge: bool,
+    #[serde(skip)]
+    acquired: Vec<u32>,
+}
+
+impl EdgeCounterChan {
+    pub fn new(chan_idx: usize, samp_rate: f64, terminal: &str, rising_edge: bool) -> Self {
+        Self {
+            name: format!("ctr{chan_idx}"),
+            samp_rate,
+            terminal: terminal.to_string(),
+            rising_edge,
+            acquired: Vec::new(),
+        }
+    }
+
+    pub fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    pub fn terminal(&self) -> &str {
+        &self.terminal
+    }
+
+    pub fn rising_edge(&self) -> bool {
+        self.rising_edge
+    }
+
+    pub fn set_acquired(&mut self, counts: Vec<u32>) {
+        self.acquired = counts;
+    }
+
+    pub fn read_counts(&self, n_samps: usize) -> Result<Vec<u32>, String> {
+        if n_samps > self.acquired.len() {
+            return Err(format!(
+                "Requested {n_samps} counts from edge-counter channel {}, but only {} have been acquired; run the device before reading",
+                self.name,
+                self.acquired.len()
+            ));
+        }
+        Ok(self.acquired[..n_samps].to_vec())
+    }
+}
+
+impl BaseChan for EdgeCounterChan {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        0.0
+    }
+
+    fn clear_edit_cache(&mut self) {
+        self.acquired.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base_streamer::fn_lib_tools::FnKindF64;
+
+    #[test]
+    fn instr_is_evaluated_relative_to_its_own_start_time() {
+        let mut early = AOChan::new(0, 1.0, 0.0, 0.0);
+        early.add_instr(FnKindF64::Linear { slope: 1.0, intercept: 0.0 }, 0.0, None).unwrap();
+
+        let mut late = AOChan::new(0, 1.0, 0.0, 0.0);
+        late.add_instr(FnKindF64::Linear { slope: 1.0, intercept: 0.0 }, 5.0, None).unwrap();
+
+        let early_samps = early.calc_nsamps(4, Some(0.0), Some(4.0)).unwrap();
+        let late_samps = late.calc_nsamps(4, Some(5.0), Some(9.0)).unwrap();
+        assert_eq!(early_samps, late_samps);
+    }
+}
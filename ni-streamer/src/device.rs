@@ -0,0 +1,255 @@
+//! NI device types: one variant per DAQmx subsystem (AO/DO/AI/DI), each carrying
+//! its own typed channel map plus the hardware routing settings common to all of
+//! them (`CommonHwCfg`).
+
+use std::collections::BTreeMap;
+
+use base_streamer::channel::BaseChan;
+use base_streamer::device::BaseDev;
+
+use serde::{Deserialize, Serialize};
+
+use crate::channel::{AOChan, AIChan, DIChan, DOChan, EdgeCounterChan};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommonHwCfg {
+    pub start_trig_in: Option<String>,
+    pub start_trig_out: Option<String>,
+    pub samp_clk_in: Option<String>,
+    pub samp_clk_out: Option<String>,
+    pub ref_clk_in: Option<String>,
+    pub min_bufwrite_timeout: Option<f64>,
+}
+
+macro_rules! dev_struct {
+    ($dev:ident, $chan:ty, $new_chan_err:expr) => {
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct $dev {
+            name: String,
+            samp_rate: f64,
+            hw_cfg: CommonHwCfg,
+            chans: BTreeMap<String, $chan>,
+        }
+
+        impl $dev {
+            pub fn new(name: &str, samp_rate: f64) -> Self {
+                Self {
+                    name: name.to_string(),
+                    samp_rate,
+                    hw_cfg: CommonHwCfg::default(),
+                    chans: BTreeMap::new(),
+                }
+            }
+
+            pub fn hw_cfg(&self) -> &CommonHwCfg {
+                &self.hw_cfg
+            }
+
+            pub fn hw_cfg_mut(&mut self) -> &mut CommonHwCfg {
+                &mut self.hw_cfg
+            }
+
+            pub fn chans(&self) -> &BTreeMap<String, $chan> {
+                &self.chans
+            }
+
+            pub fn chans_mut(&mut self) -> &mut BTreeMap<String, $chan> {
+                &mut self.chans
+            }
+
+            pub fn add_chan_sort(&mut self, chan: $chan) -> Result<(), String> {
+                let chan_name = chan.name();
+                if self.chans.contains_key(&chan_name) {
+                    return Err(format!($new_chan_err, chan_name, self.name));
+                }
+                self.chans.insert(chan_name, chan);
+                Ok(())
+            }
+        }
+
+        impl BaseDev for $dev {
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn samp_rate(&self) -> f64 {
+                self.samp_rate
+            }
+
+            fn last_instr_end_time(&self) -> f64 {
+                self.chans
+                    .values()
+                    .map(|chan| chan.last_instr_end_time())
+                    .fold(0.0, f64::max)
+            }
+
+            fn clear_edit_cache(&mut self) {
+                for chan in self.chans.values_mut() {
+                    chan.clear_edit_cache();
+                }
+            }
+        }
+    };
+}
+
+dev_struct!(AODev, AOChan, "Channel {} is already registered on AO device {}");
+dev_struct!(DODev, DOChan, "Channel {} is already registered on DO device {}");
+dev_struct!(AIDev, AIChan, "Channel {} is already registered on AI device {}");
+
+/// DI is the only subsystem that also hosts edge-counter channels, so it keeps a
+/// second, separately-namespaced channel map alongside its line channels and is
+/// written out by hand instead of with `dev_struct!`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIDev {
+    name: String,
+    samp_rate: f64,
+    hw_cfg: CommonHwCfg,
+    chans: BTreeMap<String, DIChan>,
+    counter_chans: BTreeMap<String, EdgeCounterChan>,
+}
+
+impl DIDev {
+    pub fn new(name: &str, samp_rate: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            samp_rate,
+            hw_cfg: CommonHwCfg::default(),
+            chans: BTreeMap::new(),
+            counter_chans: BTreeMap::new(),
+        }
+    }
+
+    pub fn hw_cfg(&self) -> &CommonHwCfg {
+        &self.hw_cfg
+    }
+
+    pub fn hw_cfg_mut(&mut self) -> &mut CommonHwCfg {
+        &mut self.hw_cfg
+    }
+
+    pub fn chans(&self) -> &BTreeMap<String, DIChan> {
+        &self.chans
+    }
+
+    pub fn chans_mut(&mut self) -> &mut BTreeMap<String, DIChan> {
+        &mut self.chans
+    }
+
+    pub fn add_chan_sort(&mut self, chan: DIChan) -> Result<(), String> {
+        let chan_name = chan.name();
+        if self.chans.contains_key(&chan_name) {
+            return Err(format!("Channel {} is already registered on DI device {}", chan_name, self.name));
+        }
+        self.chans.insert(chan_name, chan);
+        Ok(())
+    }
+
+    pub fn counter_chans(&self) -> &BTreeMap<String, EdgeCounterChan> {
+        &self.counter_chans
+    }
+
+    pub fn counter_chans_mut(&mut self) -> &mut BTreeMap<String, EdgeCounterChan> {
+        &mut self.counter_chans
+    }
+
+    pub fn add_counter_chan_sort(&mut self, chan: EdgeCounterChan) -> Result<(), String> {
+        let chan_name = chan.name();
+        if self.counter_chans.contains_key(&chan_name) {
+            return Err(format!("Edge-counter channel {} is already registered on DI device {}", chan_name, self.name));
+        }
+        self.counter_chans.insert(chan_name, chan);
+        Ok(())
+    }
+}
+
+impl BaseDev for DIDev {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn samp_rate(&self) -> f64 {
+        self.samp_rate
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        self.chans
+            .values()
+            .map(|chan| chan.last_instr_end_time())
+            .fold(0.0, f64::max)
+    }
+
+    fn clear_edit_cache(&mut self) {
+        for chan in self.chans.values_mut() {
+            chan.clear_edit_cache();
+        }
+        for chan in self.counter_chans.values_mut() {
+            chan.clear_edit_cache();
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum NIDev {
+    AO(AODev),
+    DO(DODev),
+    AI(AIDev),
+    DI(DIDev),
+}
+
+impl NIDev {
+    pub fn hw_cfg(&self) -> &CommonHwCfg {
+        match self {
+            NIDev::AO(dev) => dev.hw_cfg(),
+            NIDev::DO(dev) => dev.hw_cfg(),
+            NIDev::AI(dev) => dev.hw_cfg(),
+            NIDev::DI(dev) => dev.hw_cfg(),
+        }
+    }
+
+    pub fn hw_cfg_mut(&mut self) -> &mut CommonHwCfg {
+        match self {
+            NIDev::AO(dev) => dev.hw_cfg_mut(),
+            NIDev::DO(dev) => dev.hw_cfg_mut(),
+            NIDev::AI(dev) => dev.hw_cfg_mut(),
+            NIDev::DI(dev) => dev.hw_cfg_mut(),
+        }
+    }
+}
+
+impl BaseDev for NIDev {
+    fn name(&self) -> &str {
+        match self {
+            NIDev::AO(dev) => dev.name(),
+            NIDev::DO(dev) => dev.name(),
+            NIDev::AI(dev) => dev.name(),
+            NIDev::DI(dev) => dev.name(),
+        }
+    }
+
+    fn samp_rate(&self) -> f64 {
+        match self {
+            NIDev::AO(dev) => dev.samp_rate(),
+            NIDev::DO(dev) => dev.samp_rate(),
+            NIDev::AI(dev) => dev.samp_rate(),
+            NIDev::DI(dev) => dev.samp_rate(),
+        }
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        match self {
+            NIDev::AO(dev) => dev.last_instr_end_time(),
+            NIDev::DO(dev) => dev.last_instr_end_time(),
+            NIDev::AI(dev) => dev.last_instr_end_time(),
+            NIDev::DI(dev) => dev.last_instr_end_time(),
+        }
+    }
+
+    fn clear_edit_cache(&mut self) {
+        match self {
+            NIDev::AO(dev) => dev.clear_edit_cache(),
+            NIDev::DO(dev) => dev.clear_edit_cache(),
+            NIDev::AI(dev) => dev.clear_edit_cache(),
+            NIDev::DI(dev) => dev.clear_edit_cache(),
+        }
+    }
+}
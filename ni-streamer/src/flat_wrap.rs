@@ -3,22 +3,33 @@
 //! as a single "flattened" struct to be able to expose them in Python.
 
 use pyo3::prelude::*;
-use pyo3::exceptions::{PyValueError, PyKeyError, PyRuntimeError};
+use pyo3::exceptions::{PyValueError, PyKeyError, PyRuntimeError, PyIOError};
+use pyo3::types::PyDict;
 
 use base_streamer::channel::BaseChan;
 use base_streamer::device::BaseDev;
 use base_streamer::streamer::BaseStreamer;
 use base_streamer::fn_lib_tools::{FnBoxF64, FnBoxBool};
 
-use crate::channel::{AOChan, DOChan};
-use crate::device::{AODev, CommonHwCfg, DODev, NIDev};
+use crate::channel::{AOChan, DOChan, AIChan, DIChan, EdgeCounterChan};
+use crate::device::{AODev, DODev, AIDev, DIDev, NIDev};
 use crate::streamer::Streamer;
 
+/// One `(func, t, dur_spec)` instruction as handed across the pyo3 boundary in a batch call.
+type AOInstrArg = (FnBoxF64, f64, Option<(f64, bool)>);
+type DOInstrArg = (FnBoxBool, f64, Option<(f64, bool)>);
+
 #[pyclass]
 pub struct StreamerWrap {
     inner: Streamer
 }
 
+impl Default for StreamerWrap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[pymethods]
 impl StreamerWrap {
     #[new]
@@ -44,6 +55,22 @@ impl StreamerWrap {
         }
     }
 
+    pub fn add_ai_dev(&mut self, name: &str, samp_rate: f64) -> PyResult<()> {
+        let dev = AIDev::new(name, samp_rate);
+        match self.inner.add_ai_dev(dev) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
+    pub fn add_di_dev(&mut self, name: &str, samp_rate: f64) -> PyResult<()> {
+        let dev = DIDev::new(name, samp_rate);
+        match self.inner.add_di_dev(dev) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
     // region Hardware settings
     pub fn get_starts_last(&self) -> Option<String> {
         self.inner.get_starts_last()
@@ -103,6 +130,32 @@ impl StreamerWrap {
     }
     // endregion
 
+    // region Serialization
+    pub fn save_to_string(&self) -> PyResult<String> {
+        match self.inner.save_to_string() {
+            Ok(s) => Ok(s),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
+    pub fn load_from_string(&mut self, s: &str) -> PyResult<()> {
+        match self.inner.load_from_string(s) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
+    pub fn save_to_file(&self, path: &str) -> PyResult<()> {
+        let s = self.save_to_string()?;
+        std::fs::write(path, s).map_err(|err| PyIOError::new_err(err.to_string()))
+    }
+
+    pub fn load_from_file(&mut self, path: &str) -> PyResult<()> {
+        let s = std::fs::read_to_string(path).map_err(|err| PyIOError::new_err(err.to_string()))?;
+        self.load_from_string(&s)
+    }
+    // endregion
+
     // region Run control
     pub fn cfg_run(&mut self, bufsize_ms: f64) -> PyResult<()> {
         match self.inner.cfg_run_(bufsize_ms) {
@@ -124,6 +177,30 @@ impl StreamerWrap {
             Err(msg) => Err(PyRuntimeError::new_err(msg)),
         }
     }
+
+    /// Stages the per-channel AO/DO output buffers for the current fresh-compiled state and
+    /// returns a handle that `replay_run` can re-arm without recomputing a single sample.
+    /// Only the sample buffers are kept in the handle; no DAQmx task configuration is stored.
+    pub fn record_run(&mut self) -> PyResult<u64> {
+        match self.inner.record_run_() {
+            Ok(handle) => Ok(handle),
+            Err(msg) => Err(PyRuntimeError::new_err(msg)),
+        }
+    }
+
+    pub fn replay_run(&mut self, handle: u64) -> PyResult<()> {
+        match self.inner.replay_run_(handle) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyRuntimeError::new_err(msg)),
+        }
+    }
+
+    pub fn release_handle(&mut self, handle: u64) -> PyResult<()> {
+        match self.inner.release_handle_(handle) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyKeyError::new_err(msg)),
+        }
+    }
     // endregion
 }
 
@@ -182,11 +259,56 @@ impl StreamerWrap {
         }
     }
 
+    pub fn add_ai_chan(&mut self, dev_name: &str, chan_idx: usize) -> PyResult<()> {
+        let typed_dev = self.get_dev_mut(dev_name)?;
+
+        if let NIDev::AI(dev) = typed_dev {
+            let chan = AIChan::new(chan_idx, dev.samp_rate());
+            match dev.add_chan_sort(chan) {
+                Ok(()) => Ok(()),
+                Err(msg) => Err(PyKeyError::new_err(msg)),
+            }
+        } else {
+            Err(PyKeyError::new_err(format!("Cannot add analog input channel to non-AI device {dev_name}")))
+        }
+    }
+
+    pub fn add_di_chan(&mut self, dev_name: &str, port_idx: usize, line_idx: usize) -> PyResult<()> {
+        let typed_dev = self.get_dev_mut(dev_name)?;
+
+        if let NIDev::DI(dev) = typed_dev {
+            let chan = DIChan::new(port_idx, line_idx, dev.samp_rate());
+            match dev.add_chan_sort(chan) {
+                Ok(()) => Ok(()),
+                Err(msg) => Err(PyKeyError::new_err(msg)),
+            }
+        } else {
+            Err(PyKeyError::new_err(format!("Cannot add digital input channel to non-DI device {dev_name}")))
+        }
+    }
+
+    #[pyo3(signature = (dev_name, chan_idx, terminal, rising_edge=true))]
+    pub fn add_edge_counter_chan(&mut self, dev_name: &str, chan_idx: usize, terminal: &str, rising_edge: bool) -> PyResult<()> {
+        let typed_dev = self.get_dev_mut(dev_name)?;
+
+        if let NIDev::DI(dev) = typed_dev {
+            let chan = EdgeCounterChan::new(chan_idx, dev.samp_rate(), terminal, rising_edge);
+            match dev.add_counter_chan_sort(chan) {
+                Ok(()) => Ok(()),
+                Err(msg) => Err(PyKeyError::new_err(msg)),
+            }
+        } else {
+            Err(PyKeyError::new_err(format!("Cannot add an edge-counter channel to non-DI device {dev_name}")))
+        }
+    }
+
     pub fn dev_last_instr_end_time(&self, name: &str) -> PyResult<f64> {
         let typed_dev = self.get_dev(name)?;
         Ok(match typed_dev {
             NIDev::AO(dev) => dev.last_instr_end_time(),
             NIDev::DO(dev) => dev.last_instr_end_time(),
+            NIDev::AI(dev) => dev.last_instr_end_time(),
+            NIDev::DI(dev) => dev.last_instr_end_time(),
         })
     }
 
@@ -195,6 +317,8 @@ impl StreamerWrap {
         match typed_dev {
             NIDev::AO(dev) => dev.clear_edit_cache(),
             NIDev::DO(dev) => dev.clear_edit_cache(),
+            NIDev::AI(dev) => dev.clear_edit_cache(),
+            NIDev::DI(dev) => dev.clear_edit_cache(),
         };
         Ok(())
     }
@@ -205,6 +329,8 @@ impl StreamerWrap {
         let samp_rate = match typed_dev {
             NIDev::AO(dev) => dev.samp_rate(),
             NIDev::DO(dev) => dev.samp_rate(),
+            NIDev::AI(dev) => dev.samp_rate(),
+            NIDev::DI(dev) => dev.samp_rate(),
         };
         Ok(samp_rate)
     }
@@ -271,7 +397,7 @@ impl StreamerWrap {
 
     pub fn dev_get_min_bufwrite_timeout(&self, name: &str) -> PyResult<Option<f64>> {
         let ni_dev = self.get_dev(name)?;
-        Ok(ni_dev.hw_cfg().min_bufwrite_timeout.clone())
+        Ok(ni_dev.hw_cfg().min_bufwrite_timeout)
     }
 
     #[pyo3(signature = (name, min_timeout))]
@@ -365,6 +491,66 @@ impl StreamerWrap {
             )))
         }
     }
+
+    fn get_ai_chan(&self, dev_name: &str, chan_idx: usize) -> PyResult<&AIChan> {
+        let typed_dev = self.get_dev(dev_name)?;
+
+        if let NIDev::AI(dev) = typed_dev {
+            let chan_name = format!("ai{chan_idx}");
+
+            if let Some(chan) = dev.chans().get(&chan_name) {
+                Ok(chan)
+            } else {
+                Err(PyKeyError::new_err(format!(
+                    "AI device {dev_name} does not have a channel {chan_name} registered"
+                )))
+            }
+        } else {
+            Err(PyKeyError::new_err(format!(
+                "Device {dev_name} is not an AI device and cannot have AI channels"
+            )))
+        }
+    }
+
+    fn get_di_chan(&self, dev_name: &str, port: usize, line: usize) -> PyResult<&DIChan> {
+        let typed_dev = self.get_dev(dev_name)?;
+
+        if let NIDev::DI(dev) = typed_dev {
+            let chan_name = format!("port{port}/line{line}");
+
+            if let Some(chan) = dev.chans().get(&chan_name) {
+                Ok(chan)
+            } else {
+                Err(PyKeyError::new_err(format!(
+                    "DI device {dev_name} does not have a channel {chan_name} registered"
+                )))
+            }
+        } else {
+            Err(PyKeyError::new_err(format!(
+                "Device {dev_name} is not a DI device and cannot have DI channels"
+            )))
+        }
+    }
+
+    fn get_edge_counter_chan(&self, dev_name: &str, chan_idx: usize) -> PyResult<&EdgeCounterChan> {
+        let typed_dev = self.get_dev(dev_name)?;
+
+        if let NIDev::DI(dev) = typed_dev {
+            let chan_name = format!("ctr{chan_idx}");
+
+            if let Some(chan) = dev.counter_chans().get(&chan_name) {
+                Ok(chan)
+            } else {
+                Err(PyKeyError::new_err(format!(
+                    "DI device {dev_name} does not have an edge-counter channel {chan_name} registered"
+                )))
+            }
+        } else {
+            Err(PyKeyError::new_err(format!(
+                "Device {dev_name} is not a DI device and cannot have edge-counter channels"
+            )))
+        }
+    }
 }
 
 #[pymethods]
@@ -405,13 +591,25 @@ impl StreamerWrap {
             NIDev::AO(dev) => {
                 dev.chans()
                     .get(chan_name)
-                    .expect(&format!("Channel {chan_name} not found in device {dev_name}"))
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
                     .last_instr_end_time()
             },
             NIDev::DO(dev) => {
                 dev.chans()
                     .get(chan_name)
-                    .expect(&format!("Channel {chan_name} not found in device {dev_name}"))
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
+                    .last_instr_end_time()
+            }
+            NIDev::AI(dev) => {
+                dev.chans()
+                    .get(chan_name)
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
+                    .last_instr_end_time()
+            }
+            NIDev::DI(dev) => {
+                dev.chans()
+                    .get(chan_name)
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
                     .last_instr_end_time()
             }
         })
@@ -423,13 +621,25 @@ impl StreamerWrap {
             NIDev::AO(dev) => {
                 dev.chans_mut()
                     .get_mut(chan_name)
-                    .expect(&format!("Channel {chan_name} not found in device {dev_name}"))
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
                     .clear_edit_cache()
             },
             NIDev::DO(dev) => {
                 dev.chans_mut()
                     .get_mut(chan_name)
-                    .expect(&format!("Channel {chan_name} not found in device {dev_name}"))
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
+                    .clear_edit_cache()
+            }
+            NIDev::AI(dev) => {
+                dev.chans_mut()
+                    .get_mut(chan_name)
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
+                    .clear_edit_cache()
+            }
+            NIDev::DI(dev) => {
+                dev.chans_mut()
+                    .get_mut(chan_name)
+                    .unwrap_or_else(|| panic!("Channel {chan_name} not found in device {dev_name}"))
                     .clear_edit_cache()
             }
         };
@@ -464,6 +674,90 @@ impl StreamerWrap {
         }
     }
 
+    /// Applies `instrs` all-or-nothing: they're validated against a scratch clone
+    /// of the channel first, so a batch that fails partway through leaves the
+    /// channel exactly as it was, and a caller can fix the offending entry and
+    /// resubmit the same batch without the already-"applied" entries rejecting it
+    /// as duplicates.
+    #[pyo3(signature = (dev_name, chan_idx, instrs))]
+    pub fn ao_chan_add_instr_batch(
+        &mut self,
+        dev_name: &str, chan_idx: usize,
+        instrs: Vec<AOInstrArg>,
+    ) -> PyResult<()> {
+        let chan = self.get_ao_chan_mut(dev_name, chan_idx)?;
+        let mut trial = chan.clone();
+        for (idx, (func, t, dur_spec)) in instrs.into_iter().enumerate() {
+            if let Err(msg) = trial.add_instr(func.inner, t, dur_spec) {
+                return Err(PyValueError::new_err(format!(
+                    "Instruction batch for channel {chan_idx} of device {dev_name} failed at index {idx}: {msg}"
+                )));
+            }
+        }
+        *chan = trial;
+        Ok(())
+    }
+
+    /// Same all-or-nothing contract as `ao_chan_add_instr_batch`.
+    #[pyo3(signature = (dev_name, port, line, instrs))]
+    pub fn do_chan_add_instr_batch(
+        &mut self,
+        dev_name: &str, port: usize, line: usize,
+        instrs: Vec<DOInstrArg>,
+    ) -> PyResult<()> {
+        let chan = self.get_do_chan_mut(dev_name, port, line)?;
+        let mut trial = chan.clone();
+        for (idx, (func, t, dur_spec)) in instrs.into_iter().enumerate() {
+            if let Err(msg) = trial.add_instr(func.inner, t, dur_spec) {
+                return Err(PyValueError::new_err(format!(
+                    "Instruction batch for port{port}/line{line} of device {dev_name} failed at index {idx}: {msg}"
+                )));
+            }
+        }
+        *chan = trial;
+        Ok(())
+    }
+
+    /// Each per-channel batch is atomic (see `ao_chan_add_instr_batch`), but this
+    /// method itself is not: if a later `(dev_name, chan)` entry fails, earlier
+    /// entries' channels have already been committed.
+    #[pyo3(signature = (ao_instrs, do_instrs))]
+    pub fn add_instr_batch(
+        &mut self,
+        ao_instrs: Vec<(String, usize, Vec<AOInstrArg>)>,
+        do_instrs: Vec<(String, usize, usize, Vec<DOInstrArg>)>,
+    ) -> PyResult<()> {
+        for (dev_name, chan_idx, instrs) in ao_instrs {
+            self.ao_chan_add_instr_batch(&dev_name, chan_idx, instrs)?;
+        }
+        for (dev_name, port, line, instrs) in do_instrs {
+            self.do_chan_add_instr_batch(&dev_name, port, line, instrs)?;
+        }
+        Ok(())
+    }
+
+    pub fn ao_chan_set_calib(&mut self, dev_name: &str, chan_idx: usize, gain: f64, offset: f64) -> PyResult<()> {
+        let chan = self.get_ao_chan_mut(dev_name, chan_idx)?;
+        chan.set_calib(gain, offset);
+        Ok(())
+    }
+
+    pub fn ao_chan_set_calib_table(&mut self, dev_name: &str, chan_idx: usize, points: Vec<(f64, f64)>) -> PyResult<()> {
+        let chan = self.get_ao_chan_mut(dev_name, chan_idx)?;
+        match chan.set_calib_table(points) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
+    pub fn ao_chan_set_limits(&mut self, dev_name: &str, chan_idx: usize, min: f64, max: f64) -> PyResult<()> {
+        let chan = self.get_ao_chan_mut(dev_name, chan_idx)?;
+        match chan.set_limits(min, max) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
     #[pyo3(signature = (dev_name, chan_idx, n_samps, start_time=None, end_time=None))]
     pub fn ao_chan_calc_nsamps(
         &self,
@@ -491,5 +785,136 @@ impl StreamerWrap {
             Err(msg) => Err(PyValueError::new_err(msg))
         }
     }
+
+    pub fn ai_chan_read_nsamps(&self, dev_name: &str, chan_idx: usize, n_samps: usize) -> PyResult<Vec<f64>> {
+        let chan = self.get_ai_chan(dev_name, chan_idx)?;
+        match chan.read_nsamps(n_samps) {
+            Ok(samp_vec) => Ok(samp_vec),
+            Err(msg) => Err(PyRuntimeError::new_err(msg)),
+        }
+    }
+
+    pub fn di_chan_read_nsamps(&self, dev_name: &str, port: usize, line: usize, n_samps: usize) -> PyResult<Vec<bool>> {
+        let chan = self.get_di_chan(dev_name, port, line)?;
+        match chan.read_nsamps(n_samps) {
+            Ok(samp_vec) => Ok(samp_vec),
+            Err(msg) => Err(PyRuntimeError::new_err(msg)),
+        }
+    }
+
+    pub fn edge_counter_chan_read_counts(&self, dev_name: &str, chan_idx: usize, n_samps: usize) -> PyResult<Vec<u32>> {
+        let chan = self.get_edge_counter_chan(dev_name, chan_idx)?;
+        match chan.read_counts(n_samps) {
+            Ok(count_vec) => Ok(count_vec),
+            Err(msg) => Err(PyRuntimeError::new_err(msg)),
+        }
+    }
+
+    #[pyo3(signature = (samp_rate_override=None, start_time=None, end_time=None))]
+    pub fn export_all_samps(
+        &self,
+        py: Python,
+        samp_rate_override: Option<f64>,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+    ) -> PyResult<Py<PyDict>> {
+        let t0 = start_time.unwrap_or(0.0);
+        let t1 = end_time.unwrap_or_else(|| self.inner.total_run_time());
+        if t1 < t0 {
+            return Err(PyValueError::new_err(format!("end_time ({t1}) must not be earlier than start_time ({t0})")));
+        }
+
+        let result = PyDict::new(py);
+        for (dev_name, dev) in self.inner.devs() {
+            let samp_rate = samp_rate_override.unwrap_or(match dev {
+                NIDev::AO(dev) => dev.samp_rate(),
+                NIDev::DO(dev) => dev.samp_rate(),
+                NIDev::AI(dev) => dev.samp_rate(),
+                NIDev::DI(dev) => dev.samp_rate(),
+            });
+            let n_samps = ((t1 - t0) * samp_rate).round() as usize;
+
+            let chan_dict = PyDict::new(py);
+            match dev {
+                NIDev::AO(dev) => {
+                    for (chan_name, chan) in dev.chans() {
+                        let samp_vec = chan.calc_nsamps(n_samps, Some(t0), Some(t1))
+                            .map_err(PyValueError::new_err)?;
+                        chan_dict.set_item(chan_name, samp_vec)?;
+                    }
+                },
+                NIDev::DO(dev) => {
+                    for (chan_name, chan) in dev.chans() {
+                        let samp_vec = chan.calc_nsamps(n_samps, Some(t0), Some(t1))
+                            .map_err(PyValueError::new_err)?;
+                        chan_dict.set_item(chan_name, samp_vec)?;
+                    }
+                },
+                // AI/DI channels are populated by a hardware run, not by the compile-time
+                // instruction tree, so there is nothing to export here.
+                NIDev::AI(_) | NIDev::DI(_) => {},
+            }
+            result.set_item(dev_name, chan_dict)?;
+        }
+        Ok(result.into())
+    }
 }
-// endregion
\ No newline at end of file
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Golden-output regression test for the compile pipeline: a fixed AO/DO
+    /// instruction set must always export to the same per-device sample arrays,
+    /// in the same (name-sorted) device order.
+    #[test]
+    fn export_all_samps_golden_values_and_order() {
+        Python::with_gil(|py| {
+            let mut sw = StreamerWrap::new();
+            sw.add_ao_dev("Dev2", 1.0).unwrap();
+            sw.add_ao_chan("Dev2", 0, 0.0, 0.0).unwrap();
+            sw.ao_chan_add_instr("Dev2", 0, FnBoxF64::const_val(5.0), 0.0, None).unwrap();
+
+            sw.add_do_dev("Dev1", 1.0).unwrap();
+            sw.add_do_chan("Dev1", 0, 0, false, false).unwrap();
+            sw.do_chan_add_instr("Dev1", 0, 0, FnBoxBool::const_val(true), 0.0, None).unwrap();
+
+            let result = sw.export_all_samps(py, None, Some(0.0), Some(4.0)).unwrap();
+            let result = result.bind(py);
+
+            let dev_names: Vec<String> = result.keys().iter().map(|name| name.extract().unwrap()).collect();
+            assert_eq!(dev_names, vec!["Dev1", "Dev2"]);
+
+            let dev2_chans = result.get_item("Dev2").unwrap().unwrap();
+            let ao0: Vec<f64> = dev2_chans.get_item("ao0").unwrap().extract().unwrap();
+            assert_eq!(ao0, vec![5.0, 5.0, 5.0, 5.0]);
+
+            let dev1_chans = result.get_item("Dev1").unwrap().unwrap();
+            let do0: Vec<bool> = dev1_chans.get_item("port0/line0").unwrap().extract().unwrap();
+            assert_eq!(do0, vec![true, true, true, true]);
+        });
+    }
+
+    /// A batch that fails partway through must leave the channel untouched, so a
+    /// caller can fix the offending entry and resubmit the very same batch.
+    #[test]
+    fn failed_batch_leaves_channel_untouched() {
+        let mut sw = StreamerWrap::new();
+        sw.add_ao_dev("Dev1", 1000.0).unwrap();
+        sw.add_ao_chan("Dev1", 0, 0.0, 0.0).unwrap();
+
+        let bad_batch = vec![
+            (FnBoxF64::const_val(1.0), 1.0, None),
+            (FnBoxF64::const_val(2.0), 0.5, None), // out of order: must fail
+        ];
+        assert!(sw.ao_chan_add_instr_batch("Dev1", 0, bad_batch).is_err());
+
+        // If the first entry had actually been committed, resubmitting it here
+        // would fail with a "does not come after" duplicate error instead.
+        let retry = vec![(FnBoxF64::const_val(1.0), 1.0, None)];
+        assert!(sw.ao_chan_add_instr_batch("Dev1", 0, retry).is_ok());
+
+        assert_eq!(sw.ao_chan_calc_nsamps("Dev1", 0, 1, Some(1.0), Some(1.0 + 1.0 / 1000.0)).unwrap(), vec![1.0]);
+    }
+}
\ No newline at end of file
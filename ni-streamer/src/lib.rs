@@ -0,0 +1,17 @@
+//! Python extension module exposing the NI streamer through the flattened
+//! `StreamerWrap` pyclass.
+
+mod channel;
+mod device;
+mod streamer;
+mod flat_wrap;
+
+use pyo3::prelude::*;
+
+pub use flat_wrap::StreamerWrap;
+
+#[pymodule]
+fn ni_streamer(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<StreamerWrap>()?;
+    Ok(())
+}
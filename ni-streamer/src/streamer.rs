@@ -0,0 +1,331 @@
+//! The top-level NI streamer: owns the device tree, run-control state, and the
+//! record/replay handle cache used to skip re-staging unchanged output buffers.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use base_streamer::device::BaseDev;
+use base_streamer::streamer::BaseStreamer;
+
+use crate::device::{AIDev, AODev, DIDev, DODev, NIDev};
+
+/// A fully staged set of per-channel output buffers, computed once and replayable
+/// without recalculating a single sample. Keyed with `BTreeMap`, like `devs`, so
+/// iterating a stage's buffers follows device and channel name order, never hashmap
+/// bucket order.
+#[derive(Clone, Default)]
+struct RunStage {
+    ao_buffers: BTreeMap<String, BTreeMap<String, Vec<f64>>>,
+    do_buffers: BTreeMap<String, BTreeMap<String, Vec<bool>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Streamer {
+    // `BTreeMap`, not `HashMap`: `save_to_string` serializes this directly, and
+    // device order must be stable (by name) so re-saving an unchanged experiment
+    // produces a byte-identical, diff-friendly file rather than hashmap bucket order.
+    devs: BTreeMap<String, NIDev>,
+    starts_last: Option<String>,
+    ref_clk_provider: Option<(String, String)>,
+    #[serde(skip)]
+    fresh_compiled: bool,
+    #[serde(skip)]
+    armed: Option<RunStage>,
+    #[serde(skip)]
+    handles: HashMap<u64, RunStage>,
+    #[serde(skip)]
+    next_handle: u64,
+}
+
+impl Streamer {
+    pub fn new() -> Self {
+        Self {
+            devs: BTreeMap::new(),
+            starts_last: None,
+            ref_clk_provider: None,
+            fresh_compiled: false,
+            armed: None,
+            handles: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    pub fn devs(&self) -> &BTreeMap<String, NIDev> {
+        &self.devs
+    }
+
+    pub fn devs_mut(&mut self) -> &mut BTreeMap<String, NIDev> {
+        &mut self.devs
+    }
+
+    fn add_dev(&mut self, name: String, dev: NIDev) -> Result<(), String> {
+        if self.devs.contains_key(&name) {
+            return Err(format!("Device {name} is already registered"));
+        }
+        self.devs.insert(name, dev);
+        Ok(())
+    }
+
+    pub fn add_ao_dev(&mut self, dev: AODev) -> Result<(), String> {
+        self.add_dev(dev.name().to_string(), NIDev::AO(dev))
+    }
+
+    pub fn add_do_dev(&mut self, dev: DODev) -> Result<(), String> {
+        self.add_dev(dev.name().to_string(), NIDev::DO(dev))
+    }
+
+    pub fn add_ai_dev(&mut self, dev: AIDev) -> Result<(), String> {
+        self.add_dev(dev.name().to_string(), NIDev::AI(dev))
+    }
+
+    pub fn add_di_dev(&mut self, dev: DIDev) -> Result<(), String> {
+        self.add_dev(dev.name().to_string(), NIDev::DI(dev))
+    }
+
+    /// Computes the per-channel AO/DO output buffers for the current, fresh-compiled
+    /// device tree. This is the "recompute and re-stage" work that `record_run_`
+    /// lets a caller pay only once across many `replay_run_` repetitions.
+    fn build_stage(&self) -> Result<RunStage, String> {
+        let total_run_time = self.total_run_time();
+        let mut stage = RunStage::default();
+
+        for (dev_name, dev) in &self.devs {
+            match dev {
+                NIDev::AO(dev) => {
+                    let n_samps = (total_run_time * dev.samp_rate()).round() as usize;
+                    let mut chan_buffers = BTreeMap::new();
+                    for (chan_name, chan) in dev.chans() {
+                        chan_buffers.insert(chan_name.clone(), chan.calc_nsamps(n_samps, None, None)?);
+                    }
+                    stage.ao_buffers.insert(dev_name.clone(), chan_buffers);
+                }
+                NIDev::DO(dev) => {
+                    let n_samps = (total_run_time * dev.samp_rate()).round() as usize;
+                    let mut chan_buffers = BTreeMap::new();
+                    for (chan_name, chan) in dev.chans() {
+                        chan_buffers.insert(chan_name.clone(), chan.calc_nsamps(n_samps, None, None)?);
+                    }
+                    stage.do_buffers.insert(dev_name.clone(), chan_buffers);
+                }
+                // AI/DI channels are filled by a run, not staged ahead of one.
+                NIDev::AI(_) | NIDev::DI(_) => {}
+            }
+        }
+        Ok(stage)
+    }
+
+    /// Simulates driving the already-staged output buffers to hardware. There is no
+    /// real DAQmx device in this crate to talk to, so this only validates the stage
+    /// against the device tree's current timing.
+    ///
+    /// Partly guards against a stale handle: if the device tree was edited after a
+    /// stage was recorded without going through `clear_edit_cache`/`compile` (which
+    /// invalidate outstanding handles), the stage is rejected when a staged buffer
+    /// length no longer matches what the current timing would produce. An edit that
+    /// leaves the total run time unchanged is not detected.
+    fn execute_stage(&mut self, stage: &RunStage) -> Result<(), String> {
+        let total_run_time = self.total_run_time();
+        for (dev_name, dev) in &self.devs {
+            let expected_len = (total_run_time * dev.samp_rate()).round() as usize;
+            let staged_lens: Vec<usize> = match dev {
+                NIDev::AO(_) => stage.ao_buffers.get(dev_name).map(|chans| chans.values().map(|buf| buf.len()).collect()).unwrap_or_default(),
+                NIDev::DO(_) => stage.do_buffers.get(dev_name).map(|chans| chans.values().map(|buf| buf.len()).collect()).unwrap_or_default(),
+                NIDev::AI(_) | NIDev::DI(_) => Vec::new(),
+            };
+            for len in staged_lens {
+                if len != expected_len {
+                    return Err(format!(
+                        "Staged buffer for device {dev_name} is out of date (has {len} samples, expected {expected_len}); recompile and record the run again"
+                    ));
+                }
+            }
+        }
+
+        // There is no real DAQmx input hardware in this crate to acquire from, so
+        // every AI/DI/edge-counter channel is simply filled with its type's zero
+        // value for the run's duration.
+        for dev in self.devs.values_mut() {
+            let expected_len = (total_run_time * dev.samp_rate()).round() as usize;
+            match dev {
+                NIDev::AI(dev) => {
+                    for chan in dev.chans_mut().values_mut() {
+                        chan.set_acquired(vec![0.0; expected_len]);
+                    }
+                }
+                NIDev::DI(dev) => {
+                    for chan in dev.chans_mut().values_mut() {
+                        chan.set_acquired(vec![false; expected_len]);
+                    }
+                    for chan in dev.counter_chans_mut().values_mut() {
+                        chan.set_acquired(vec![0; expected_len]);
+                    }
+                }
+                NIDev::AO(_) | NIDev::DO(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_run_(&mut self) -> Result<u64, String> {
+        let stage = self.build_stage()?;
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(handle, stage);
+        Ok(handle)
+    }
+
+    pub fn replay_run_(&mut self, handle: u64) -> Result<(), String> {
+        let stage = self
+            .handles
+            .get(&handle)
+            .cloned()
+            .ok_or_else(|| format!("No recorded run with handle {handle}"))?;
+        self.armed = Some(stage.clone());
+        self.execute_stage(&stage)
+    }
+
+    pub fn release_handle_(&mut self, handle: u64) -> Result<(), String> {
+        self.handles
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or_else(|| format!("No recorded run with handle {handle}"))
+    }
+
+    /// Serializes the device tree and run-control settings to pretty-printed JSON.
+    /// Record/replay handles and compile state are not part of this snapshot, since
+    /// they are derived, in-memory caches, not durable configuration.
+    pub fn save_to_string(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    pub fn load_from_string(&mut self, s: &str) -> Result<(), String> {
+        *self = serde_json::from_str(s).map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
+
+impl Default for Streamer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BaseStreamer for Streamer {
+    fn get_starts_last(&self) -> Option<String> {
+        self.starts_last.clone()
+    }
+
+    fn set_starts_last(&mut self, name: Option<String>) {
+        self.starts_last = name;
+    }
+
+    fn get_ref_clk_provider(&self) -> Option<(String, String)> {
+        self.ref_clk_provider.clone()
+    }
+
+    fn set_ref_clk_provider(&mut self, provider: Option<(String, String)>) {
+        self.ref_clk_provider = provider;
+    }
+
+    fn reset_all(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn last_instr_end_time(&self) -> f64 {
+        self.devs.values().map(|dev| dev.last_instr_end_time()).fold(0.0, f64::max)
+    }
+
+    fn total_run_time(&self) -> f64 {
+        self.last_instr_end_time()
+    }
+
+    fn compile(&mut self, stop_time: Option<f64>) -> Result<f64, String> {
+        let last_instr_end_time = self.last_instr_end_time();
+        if let Some(stop_time) = stop_time {
+            if stop_time < last_instr_end_time {
+                return Err(format!(
+                    "Requested stop_time ({stop_time}) is earlier than the last instruction's end time ({last_instr_end_time})"
+                ));
+            }
+        }
+        self.fresh_compiled = true;
+        self.handles.clear();
+        self.armed = None;
+        Ok(stop_time.unwrap_or(last_instr_end_time))
+    }
+
+    fn is_fresh_compiled(&self) -> bool {
+        self.fresh_compiled
+    }
+
+    fn clear_edit_cache(&mut self) {
+        for dev in self.devs.values_mut() {
+            dev.clear_edit_cache();
+        }
+        self.fresh_compiled = false;
+        self.handles.clear();
+        self.armed = None;
+    }
+
+    fn add_reset_instr(&mut self, reset_time: Option<f64>) -> Result<(), String> {
+        let reset_time = reset_time.unwrap_or_else(|| self.last_instr_end_time());
+        for dev in self.devs.values_mut() {
+            if let NIDev::AO(dev) = dev {
+                for chan in dev.chans_mut().values_mut() {
+                    let rst_val = chan.rst_val();
+                    chan.add_instr(base_streamer::fn_lib_tools::FnKindF64::Const { val: rst_val }, reset_time, None)?;
+                }
+            }
+            if let NIDev::DO(dev) = dev {
+                for chan in dev.chans_mut().values_mut() {
+                    let rst_val = chan.rst_val();
+                    chan.add_instr(base_streamer::fn_lib_tools::FnKindBool::Const { val: rst_val }, reset_time, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn cfg_run_(&mut self, bufsize_ms: f64) -> Result<(), String> {
+        if bufsize_ms <= 0.0 {
+            return Err(format!("bufsize_ms must be positive, got {bufsize_ms}"));
+        }
+        self.armed = Some(self.build_stage()?);
+        Ok(())
+    }
+
+    fn stream_run_(&mut self, _calc_next: bool) -> Result<(), String> {
+        let stage = self.armed.clone().ok_or_else(|| "cfg_run must be called before stream_run".to_string())?;
+        self.execute_stage(&stage)
+    }
+
+    fn close_run_(&mut self) -> Result<(), String> {
+        self.armed = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base_streamer::fn_lib_tools::FnKindF64;
+    use crate::channel::AOChan;
+
+    #[test]
+    fn save_load_round_trip_preserves_state() {
+        let mut streamer = Streamer::new();
+        let mut dev = AODev::new("Dev1", 1000.0);
+        dev.add_chan_sort(AOChan::new(0, 1000.0, 0.0, 0.0)).unwrap();
+        streamer.add_ao_dev(dev).unwrap();
+        if let NIDev::AO(dev) = streamer.devs_mut().get_mut("Dev1").unwrap() {
+            dev.chans_mut().get_mut("ao0").unwrap().add_instr(FnKindF64::Const { val: 1.0 }, 0.0, None).unwrap();
+        }
+
+        let saved = streamer.save_to_string().unwrap();
+        let mut loaded = Streamer::new();
+        loaded.load_from_string(&saved).unwrap();
+
+        assert_eq!(loaded.save_to_string().unwrap(), saved);
+    }
+}